@@ -0,0 +1,59 @@
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::run_relay;
+
+/// How often we poll `relay.exe list-ports` while no true hotplug callback
+/// is available on this platform.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+static LAST_PORTS: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+
+#[derive(Serialize, Clone)]
+struct PortsChangedPayload {
+  ports: Vec<serde_json::Value>,
+  added: Vec<String>,
+  removed: Vec<String>,
+}
+
+fn port_name(entry: &serde_json::Value) -> Option<String> {
+  entry.get("name")?.as_str().map(str::to_string)
+}
+
+/// Spawns a background thread that watches for serial ports arriving and
+/// leaving, emitting `relay://ports-changed` whenever the set changes.
+///
+/// There's no true OS hotplug callback wired up yet, so this falls back to
+/// polling `list-ports --json` on a fixed interval and diffing the port
+/// names against the last-known snapshot.
+pub fn spawn(app: AppHandle) {
+  std::thread::spawn(move || loop {
+    if let Ok(result) = run_relay(&app, &["list-ports".to_string(), "--json".to_string()]) {
+      if result.ok {
+        if let Ok(serde_json::Value::Array(ports)) = serde_json::from_str(&result.stdout) {
+          let names: Vec<String> = ports.iter().filter_map(port_name).collect();
+
+          let snapshot = LAST_PORTS.get_or_init(|| Mutex::new(Vec::new()));
+          let mut last = snapshot.lock().unwrap();
+
+          if *last != names {
+            let added: Vec<String> = names.iter().filter(|n| !last.contains(n)).cloned().collect();
+            let removed: Vec<String> = last.iter().filter(|n| !names.contains(n)).cloned().collect();
+
+            *last = names;
+            drop(last);
+
+            let _ = app.emit(
+              "relay://ports-changed",
+              PortsChangedPayload { ports, added, removed },
+            );
+          }
+        }
+      }
+    }
+
+    std::thread::sleep(POLL_INTERVAL);
+  });
+}
@@ -0,0 +1,149 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command as TokioCommand};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[derive(Serialize, Clone)]
+struct JobOutputPayload {
+  job_id: u64,
+  stream: &'static str,
+  line: String,
+}
+
+#[derive(Serialize, Clone)]
+struct JobDonePayload {
+  job_id: u64,
+  code: Option<i32>,
+}
+
+/// Tracks the relay.exe child processes spawned for long-running, timed
+/// commands (e.g. a `--seconds` pulse) so their output can be streamed to
+/// the UI and, if needed, the job can be cancelled mid-flight.
+#[derive(Default)]
+pub struct JobRegistry {
+  next_id: AtomicU64,
+  children: Mutex<HashMap<u64, Arc<tokio::sync::Mutex<Child>>>>,
+}
+
+impl JobRegistry {
+  fn allocate_id(&self) -> u64 {
+    self.next_id.fetch_add(1, Ordering::SeqCst)
+  }
+}
+
+/// Spawns `relay_exe` with `args`, returning its job id immediately. Stdout
+/// and stderr lines are streamed to the frontend as `relay://job-output`
+/// events as they arrive, followed by a single `relay://job-done` once the
+/// process exits.
+///
+/// Callers must release the persistent relay session's hold on the serial
+/// port first (see `crate::release_session_port`) — the port can only be
+/// open in one process at a time.
+pub async fn spawn(
+  app: AppHandle,
+  registry: &JobRegistry,
+  relay_exe: &Path,
+  args: Vec<String>,
+) -> Result<u64, String> {
+  let mut cmd = TokioCommand::new(relay_exe);
+  cmd.args(&args);
+  cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+  #[cfg(windows)]
+  {
+    cmd.creation_flags(CREATE_NO_WINDOW);
+  }
+
+  let mut child = cmd
+    .spawn()
+    .map_err(|e| format!("Failed to run {}: {e}", relay_exe.display()))?;
+
+  let stdout = child.stdout.take().ok_or("relay.exe gave us no stdout")?;
+  let stderr = child.stderr.take().ok_or("relay.exe gave us no stderr")?;
+
+  let job_id = registry.allocate_id();
+  let child = Arc::new(tokio::sync::Mutex::new(child));
+  registry.children.lock().unwrap().insert(job_id, child.clone());
+
+  let out_app = app.clone();
+  let out_task = tauri::async_runtime::spawn(async move {
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+      let _ = out_app.emit(
+        "relay://job-output",
+        JobOutputPayload { job_id, stream: "stdout", line },
+      );
+    }
+  });
+
+  let err_app = app.clone();
+  let err_task = tauri::async_runtime::spawn(async move {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+      let _ = err_app.emit(
+        "relay://job-output",
+        JobOutputPayload { job_id, stream: "stderr", line },
+      );
+    }
+  });
+
+  tauri::async_runtime::spawn(async move {
+    // Poll rather than `child.lock().await.wait().await` — holding the
+    // mutex across a blocking wait would starve `cancel`'s `kill()` call
+    // until the process had already exited on its own.
+    let code = loop {
+      let mut guard = child.lock().await;
+      match guard.try_wait() {
+        Ok(Some(status)) => break status.code(),
+        Ok(None) => {
+          drop(guard);
+          tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        Err(_) => break None,
+      }
+    };
+
+    let _ = out_task.await;
+    let _ = err_task.await;
+
+    registry_forget(&app, job_id);
+    let _ = app.emit("relay://job-done", JobDonePayload { job_id, code });
+  });
+
+  Ok(job_id)
+}
+
+fn registry_forget(app: &AppHandle, job_id: u64) {
+  if let Some(registry) = app.try_state::<JobRegistry>() {
+    registry.children.lock().unwrap().remove(&job_id);
+  }
+}
+
+/// Kills the child process backing `job_id`, if it's still running.
+pub fn cancel(registry: &JobRegistry, job_id: u64) -> Result<(), String> {
+  let child = registry
+    .children
+    .lock()
+    .unwrap()
+    .get(&job_id)
+    .cloned()
+    .ok_or_else(|| format!("No running job with id {job_id}"))?;
+
+  tauri::async_runtime::spawn(async move {
+    let _ = child.lock().await.kill().await;
+  });
+
+  Ok(())
+}
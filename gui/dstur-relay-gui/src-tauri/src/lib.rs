@@ -1,7 +1,19 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager, State};
+
+use jobs::JobRegistry;
+use sequences::SequenceStep;
+use server::ControlServer;
+use session::RelaySession;
+use std::collections::HashMap;
+
+mod hotplug;
+mod jobs;
+mod sequences;
+mod server;
+mod session;
 
 static RELAY_PATH: OnceLock<PathBuf> = OnceLock::new();
 
@@ -11,8 +23,8 @@ use std::os::windows::process::CommandExt;
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
-#[derive(Serialize)]
-struct CmdResult {
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct CmdResult {
   ok: bool,
   code: Option<i32>,
   stdout: String,
@@ -62,7 +74,7 @@ fn relay_exe_path(app: &AppHandle) -> Result<PathBuf, String> {
 }
 
 
-fn run_relay(app: &AppHandle, args: &[String]) -> Result<CmdResult, String> {
+pub(crate) fn run_relay(app: &AppHandle, args: &[String]) -> Result<CmdResult, String> {
   let exe = relay_exe_path(app)?;
 
   let mut cmd = std::process::Command::new(&exe);
@@ -93,29 +105,13 @@ fn relay_list_ports(app: AppHandle) -> Result<CmdResult, String> {
 }
 
 #[tauri::command]
-fn relay_status(app: AppHandle, port: Option<String>, target: String) -> Result<CmdResult, String> {
-  let mut args: Vec<String> = Vec::new();
-  if let Some(p) = port {
-    args.push("--port".into());
-    args.push(p);
-  }
-  args.push("status".into());
-  args.push(target);
-  run_relay(&app, &args)
+async fn relay_status(app: AppHandle, port: Option<String>, target: String) -> Result<CmdResult, String> {
+  let line = format!("status {target}");
+  run_on_session(app, port, line).await
 }
 
-#[tauri::command]
-fn relay_set(
-  app: AppHandle,
-  port: Option<String>,
-  relay: u8,
-  state: String,
-  seconds: Option<f32>,
-) -> Result<CmdResult, String> {
-  if relay < 1 || relay > 8 {
-    return Err("Relay number must be 1..8".into());
-  }
-
+/// Builds the `relay.exe` argument list for a `relay <n> <state>` invocation.
+pub(crate) fn build_relay_args(port: Option<String>, relay: u8, state: String, seconds: Option<f32>) -> Vec<String> {
   let mut args: Vec<String> = Vec::new();
   if let Some(p) = port {
     args.push("--port".into());
@@ -130,16 +126,11 @@ fn relay_set(
     args.push(s.to_string());
   }
 
-  run_relay(&app, &args)
+  args
 }
 
-#[tauri::command]
-fn relay_all(
-  app: AppHandle,
-  port: Option<String>,
-  state: String,
-  seconds: Option<f32>,
-) -> Result<CmdResult, String> {
+/// Builds the `relay.exe` argument list for an `all <state>` invocation.
+pub(crate) fn build_all_args(port: Option<String>, state: String, seconds: Option<f32>) -> Vec<String> {
   let mut args: Vec<String> = Vec::new();
   if let Some(p) = port {
     args.push("--port".into());
@@ -153,17 +144,201 @@ fn relay_all(
     args.push(s.to_string());
   }
 
-  run_relay(&app, &args)
+  args
+}
+
+/// Result of `relay_set`/`relay_all`: instant commands (no `seconds`) run on
+/// the fast persistent session and resolve immediately; timed pulses run as
+/// a streamed, cancellable job instead so the invoke doesn't block for the
+/// full duration.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum SetOutcome {
+  Immediate { result: CmdResult },
+  Job { job_id: u64 },
+}
+
+/// Runs `line` on the persistent relay session without blocking the async
+/// runtime thread on its blocking stdin/stdout I/O.
+async fn run_on_session(app: AppHandle, port: Option<String>, line: String) -> Result<CmdResult, String> {
+  tauri::async_runtime::spawn_blocking(move || {
+    let session = app.state::<Mutex<RelaySession>>();
+    session.lock().unwrap().send(&app, port, &line)
+  })
+  .await
+  .map_err(|e| format!("Relay session task panicked: {e}"))?
+}
+
+/// `relay.exe --interactive` opens the serial port for the lifetime of the
+/// session, and serial ports can only be opened by one process at a time.
+/// Anything that needs to spawn its own one-off `relay.exe` — a timed job,
+/// a sequence step, a remote command — must release the session's hold on
+/// the port first, or the port-open call in the new process fails. The
+/// session respawns lazily (see `RelaySession::send`) the next time an
+/// instant `relay_set`/`relay_all`/`relay_status` call needs it.
+pub(crate) fn release_session_port(app: &AppHandle) {
+  if let Some(session) = app.try_state::<Mutex<RelaySession>>() {
+    session.lock().unwrap().release();
+  }
+}
+
+#[tauri::command]
+async fn relay_set(
+  app: AppHandle,
+  jobs: State<'_, JobRegistry>,
+  port: Option<String>,
+  relay: u8,
+  state: String,
+  seconds: Option<f32>,
+) -> Result<SetOutcome, String> {
+  if !(1..=8).contains(&relay) {
+    return Err("Relay number must be 1..8".into());
+  }
+
+  if seconds.is_none() {
+    let line = format!("relay {relay} {state}");
+    let result = run_on_session(app, port, line).await?;
+    return Ok(SetOutcome::Immediate { result });
+  }
+
+  release_session_port(&app);
+  let exe = relay_exe_path(&app)?;
+  let args = build_relay_args(port, relay, state, seconds);
+  let job_id = jobs::spawn(app, &jobs, &exe, args).await?;
+  Ok(SetOutcome::Job { job_id })
+}
+
+#[tauri::command]
+async fn relay_all(
+  app: AppHandle,
+  jobs: State<'_, JobRegistry>,
+  port: Option<String>,
+  state: String,
+  seconds: Option<f32>,
+) -> Result<SetOutcome, String> {
+  if seconds.is_none() {
+    let line = format!("all {state}");
+    let result = run_on_session(app, port, line).await?;
+    return Ok(SetOutcome::Immediate { result });
+  }
+
+  release_session_port(&app);
+  let exe = relay_exe_path(&app)?;
+  let args = build_all_args(port, state, seconds);
+  let job_id = jobs::spawn(app, &jobs, &exe, args).await?;
+  Ok(SetOutcome::Job { job_id })
+}
+
+#[tauri::command]
+fn relay_cancel(jobs: State<JobRegistry>, job_id: u64) -> Result<(), String> {
+  jobs::cancel(&jobs, job_id)
+}
+
+#[tauri::command]
+fn relay_save_sequence(app: AppHandle, name: String, steps: Vec<SequenceStep>) -> Result<(), String> {
+  sequences::save_sequence(&app, name, steps)
+}
+
+#[tauri::command]
+fn relay_list_sequences(app: AppHandle) -> Result<HashMap<String, Vec<SequenceStep>>, String> {
+  sequences::list_sequences(&app)
+}
+
+#[tauri::command]
+fn relay_delete_sequence(app: AppHandle, name: String) -> Result<(), String> {
+  sequences::delete_sequence(&app, &name)
+}
+
+#[tauri::command]
+fn relay_run_sequence(app: AppHandle, name: String, port: Option<String>) -> Result<(), String> {
+  sequences::run_sequence(app, name, port);
+  Ok(())
+}
+
+#[tauri::command]
+fn relay_server_start(
+  app: AppHandle,
+  server: State<ControlServer>,
+  addr: Option<String>,
+  token: String,
+) -> Result<(), String> {
+  let addr = addr.unwrap_or_else(|| server::DEFAULT_ADDR.to_string());
+  server.start(app, addr, token)
+}
+
+#[tauri::command]
+fn relay_server_stop(server: State<ControlServer>) {
+  server.stop();
+}
+
+#[tauri::command]
+fn relay_connect(
+  app: AppHandle,
+  session: State<Mutex<RelaySession>>,
+  port: Option<String>,
+) -> Result<(), String> {
+  session.lock().unwrap().connect(&app, port)
+}
+
+#[tauri::command]
+fn relay_disconnect(session: State<Mutex<RelaySession>>) {
+  session.lock().unwrap().disconnect();
 }
 
 pub fn run() {
   tauri::Builder::default()
+    .manage(Mutex::new(RelaySession::default()))
+    .manage(JobRegistry::default())
+    .manage(ControlServer::default())
+    .setup(|app| {
+      hotplug::spawn(app.handle().clone());
+      server::autostart_if_enabled(app.handle());
+      Ok(())
+    })
     .invoke_handler(tauri::generate_handler![
       relay_list_ports,
       relay_status,
       relay_set,
-      relay_all
+      relay_all,
+      relay_connect,
+      relay_disconnect,
+      relay_cancel,
+      relay_save_sequence,
+      relay_list_sequences,
+      relay_delete_sequence,
+      relay_run_sequence,
+      relay_server_start,
+      relay_server_stop
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn build_relay_args_without_port_or_seconds() {
+    let args = build_relay_args(None, 3, "on".into(), None);
+    assert_eq!(args, vec!["relay", "3", "on"]);
+  }
+
+  #[test]
+  fn build_relay_args_with_port_and_seconds() {
+    let args = build_relay_args(Some("COM3".into()), 1, "off".into(), Some(1.5));
+    assert_eq!(args, vec!["--port", "COM3", "relay", "1", "off", "--seconds", "1.5"]);
+  }
+
+  #[test]
+  fn build_all_args_without_port_or_seconds() {
+    let args = build_all_args(None, "on".into(), None);
+    assert_eq!(args, vec!["all", "on"]);
+  }
+
+  #[test]
+  fn build_all_args_with_port_and_seconds() {
+    let args = build_all_args(Some("COM4".into()), "off".into(), Some(2.0));
+    assert_eq!(args, vec!["--port", "COM4", "all", "off", "--seconds", "2"]);
+  }
+}
@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::{build_all_args, build_relay_args, release_session_port, run_relay, CmdResult};
+
+/// One step of a saved relay sequence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceStep {
+  pub action: String,
+  pub relay: Option<u8>,
+  pub state: String,
+  pub seconds: Option<f32>,
+  pub delay_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SequenceFile {
+  #[serde(default)]
+  sequences: HashMap<String, Vec<SequenceStep>>,
+}
+
+#[derive(Serialize, Clone)]
+struct SequenceStepPayload {
+  sequence: String,
+  index: usize,
+  total: usize,
+  step: SequenceStep,
+  ok: bool,
+  output: Option<CmdResult>,
+  error: Option<String>,
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+  let dir = app
+    .path()
+    .app_config_dir()
+    .map_err(|e| format!("Failed to resolve app config dir: {e}"))?;
+  fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+  Ok(dir.join("sequences.toml"))
+}
+
+fn load(app: &AppHandle) -> Result<SequenceFile, String> {
+  let path = config_path(app)?;
+  if !path.exists() {
+    return Ok(SequenceFile::default());
+  }
+
+  let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+  toml::from_str(&text).map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+}
+
+fn save(app: &AppHandle, file: &SequenceFile) -> Result<(), String> {
+  let path = config_path(app)?;
+  let text = toml::to_string_pretty(file).map_err(|e| format!("Failed to serialize sequences: {e}"))?;
+  fs::write(&path, text).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+fn validate_step(step: &SequenceStep) -> Result<(), String> {
+  match step.action.as_str() {
+    "relay" => {
+      let relay = step.relay.ok_or("A \"relay\" step must set `relay`")?;
+      if !(1..=8).contains(&relay) {
+        return Err("Relay number must be 1..8".into());
+      }
+      Ok(())
+    }
+    "all" => Ok(()),
+    other => Err(format!("Unknown sequence step action: {other}")),
+  }
+}
+
+fn validate(steps: &[SequenceStep]) -> Result<(), String> {
+  steps.iter().try_for_each(validate_step)
+}
+
+pub fn save_sequence(app: &AppHandle, name: String, steps: Vec<SequenceStep>) -> Result<(), String> {
+  validate(&steps)?;
+  let mut file = load(app)?;
+  file.sequences.insert(name, steps);
+  save(app, &file)
+}
+
+pub fn list_sequences(app: &AppHandle) -> Result<HashMap<String, Vec<SequenceStep>>, String> {
+  Ok(load(app)?.sequences)
+}
+
+pub fn delete_sequence(app: &AppHandle, name: &str) -> Result<(), String> {
+  let mut file = load(app)?;
+  file.sequences.remove(name);
+  save(app, &file)
+}
+
+/// Runs a saved sequence step-by-step on a spawned task, emitting
+/// `relay://sequence-step` after each step and honoring `delay_ms` between
+/// them. Runs fire-and-forget; progress is observed entirely via events.
+pub fn run_sequence(app: AppHandle, name: String, port: Option<String>) {
+  tauri::async_runtime::spawn(async move {
+    // Each step below spawns its own one-off relay.exe; release the
+    // session's hold on the port up front rather than fighting it step by
+    // step.
+    release_session_port(&app);
+
+    let steps = match load(&app) {
+      Ok(file) => match file.sequences.get(&name) {
+        Some(steps) => steps.clone(),
+        None => {
+          let _ = app.emit(
+            "relay://sequence-step",
+            SequenceStepPayload {
+              sequence: name.clone(),
+              index: 0,
+              total: 0,
+              step: SequenceStep { action: "none".into(), relay: None, state: String::new(), seconds: None, delay_ms: None },
+              ok: false,
+              output: None,
+              error: Some(format!("No such sequence: {name}")),
+            },
+          );
+          return;
+        }
+      },
+      Err(e) => {
+        let _ = app.emit(
+          "relay://sequence-step",
+          SequenceStepPayload {
+            sequence: name.clone(),
+            index: 0,
+            total: 0,
+            step: SequenceStep { action: "none".into(), relay: None, state: String::new(), seconds: None, delay_ms: None },
+            ok: false,
+            output: None,
+            error: Some(e),
+          },
+        );
+        return;
+      }
+    };
+
+    let total = steps.len();
+
+    for (index, step) in steps.into_iter().enumerate() {
+      let args = match validate_step(&step) {
+        Err(e) => {
+          let _ = app.emit(
+            "relay://sequence-step",
+            SequenceStepPayload { sequence: name.clone(), index, total, step: step.clone(), ok: false, output: None, error: Some(e) },
+          );
+          continue;
+        }
+        Ok(()) => match step.action.as_str() {
+          "relay" => build_relay_args(port.clone(), step.relay.expect("validated above"), step.state.clone(), step.seconds),
+          "all" => build_all_args(port.clone(), step.state.clone(), step.seconds),
+          _ => unreachable!("validate_step rejects unknown actions"),
+        },
+      };
+
+      let blocking_app = app.clone();
+      let result = tauri::async_runtime::spawn_blocking(move || run_relay(&blocking_app, &args)).await;
+
+      let (ok, output, error) = match result {
+        Ok(Ok(r)) => (r.ok, Some(r), None),
+        Ok(Err(e)) => (false, None, Some(e)),
+        Err(e) => (false, None, Some(e.to_string())),
+      };
+
+      let delay_ms = step.delay_ms;
+
+      let _ = app.emit(
+        "relay://sequence-step",
+        SequenceStepPayload { sequence: name.clone(), index, total, step, ok, output, error },
+      );
+
+      if let Some(ms) = delay_ms {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+      }
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn relay_step(relay: Option<u8>) -> SequenceStep {
+    SequenceStep { action: "relay".into(), relay, state: "on".into(), seconds: None, delay_ms: None }
+  }
+
+  #[test]
+  fn relay_step_in_range_is_valid() {
+    assert!(validate_step(&relay_step(Some(1))).is_ok());
+    assert!(validate_step(&relay_step(Some(8))).is_ok());
+  }
+
+  #[test]
+  fn relay_step_out_of_range_is_rejected() {
+    assert!(validate_step(&relay_step(Some(0))).is_err());
+    assert!(validate_step(&relay_step(Some(9))).is_err());
+  }
+
+  #[test]
+  fn relay_step_without_relay_number_is_rejected() {
+    assert!(validate_step(&relay_step(None)).is_err());
+  }
+
+  #[test]
+  fn all_step_is_always_valid() {
+    let step = SequenceStep { action: "all".into(), relay: None, state: "off".into(), seconds: None, delay_ms: None };
+    assert!(validate_step(&step).is_ok());
+  }
+
+  #[test]
+  fn unknown_action_is_rejected() {
+    let step = SequenceStep { action: "bogus".into(), relay: None, state: "on".into(), seconds: None, delay_ms: None };
+    assert!(validate_step(&step).is_err());
+  }
+}
@@ -0,0 +1,302 @@
+use serde::Deserialize;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::{build_all_args, build_relay_args, release_session_port, run_relay, CmdResult};
+
+/// How long a connection may sit idle before we give up on it. Keeps a
+/// handful of idle/half-open loopback clients from pinning handler threads
+/// forever.
+const CLIENT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Max bytes accepted for a single line (the token, or one JSON command)
+/// before the connection is treated as abusive and dropped. Without this, a
+/// client that never sends a `\n` would have `read_line` grow its `String`
+/// without bound for the full `CLIENT_READ_TIMEOUT`.
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+/// Default bind address for the remote control listener: loopback-only
+/// unless the caller explicitly asks for something else.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+
+/// On-disk config read at startup, `<app config dir>/server.toml`, e.g.:
+/// ```toml
+/// enabled = true
+/// addr = "127.0.0.1:7878"
+/// token = "changeme"
+/// ```
+#[derive(Deserialize)]
+struct ServerConfig {
+  #[serde(default)]
+  enabled: bool,
+  #[serde(default = "default_addr")]
+  addr: String,
+  token: String,
+}
+
+fn default_addr() -> String {
+  DEFAULT_ADDR.to_string()
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+  let dir = app
+    .path()
+    .app_config_dir()
+    .map_err(|e| format!("Failed to resolve app config dir: {e}"))?;
+  Ok(dir.join("server.toml"))
+}
+
+/// Binds the control server at startup if `<app config dir>/server.toml`
+/// exists and has `enabled = true`. Missing/unparseable config or a missing
+/// token just leaves the server off — this is opt-in.
+pub fn autostart_if_enabled(app: &AppHandle) {
+  let Ok(path) = config_path(app) else { return };
+  if !path.exists() {
+    return;
+  }
+
+  let Ok(text) = fs::read_to_string(&path) else { return };
+  let Ok(config) = toml::from_str::<ServerConfig>(&text) else { return };
+
+  if !config.enabled {
+    return;
+  }
+
+  let Some(server) = app.try_state::<ControlServer>() else { return };
+  if let Err(e) = server.start(app.clone(), config.addr, config.token) {
+    eprintln!("relay control server: failed to autostart: {e}");
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+enum RemoteCommand {
+  Relay {
+    relay: u8,
+    state: String,
+    seconds: Option<f32>,
+    port: Option<String>,
+  },
+  All {
+    state: String,
+    seconds: Option<f32>,
+    port: Option<String>,
+  },
+  Status {
+    target: String,
+    port: Option<String>,
+  },
+  ListPorts,
+}
+
+/// Opt-in TCP listener that lets remote clients (home-automation scripts,
+/// another machine, a physical button bridge) drive the relay board without
+/// going through the Tauri UI. Commands are newline-delimited JSON objects;
+/// the connection's first line must be the configured shared-secret token.
+#[derive(Default)]
+pub struct ControlServer {
+  stop_flag: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl ControlServer {
+  pub fn start(&self, app: AppHandle, addr: String, token: String) -> Result<(), String> {
+    if token.trim().is_empty() {
+      return Err("Relay control server requires a non-empty token".into());
+    }
+
+    let mut guard = self.stop_flag.lock().unwrap();
+    if guard.is_some() {
+      return Err("Relay control server is already running".into());
+    }
+
+    let listener = TcpListener::bind(&addr).map_err(|e| format!("Failed to bind {addr}: {e}"))?;
+    listener
+      .set_nonblocking(true)
+      .map_err(|e| format!("Failed to configure listener: {e}"))?;
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    *guard = Some(stop_flag.clone());
+    drop(guard);
+
+    std::thread::spawn(move || {
+      while !stop_flag.load(Ordering::SeqCst) {
+        match listener.accept() {
+          Ok((stream, _)) => {
+            let app = app.clone();
+            let token = token.clone();
+            std::thread::spawn(move || handle_client(app, stream, token));
+          }
+          Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+            std::thread::sleep(Duration::from_millis(200));
+          }
+          Err(_) => break,
+        }
+      }
+    });
+
+    Ok(())
+  }
+
+  pub fn stop(&self) {
+    if let Some(flag) = self.stop_flag.lock().unwrap().take() {
+      flag.store(true, Ordering::SeqCst);
+    }
+  }
+}
+
+/// Compares two byte strings in constant time w.r.t. their contents, so a
+/// remote client probing the shared-secret token can't learn how many
+/// leading bytes it got right from response latency. Length is not secret
+/// (the token's length isn't sensitive), so this only needs to avoid
+/// short-circuiting on content, not on length.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  if a.len() != b.len() {
+    return false;
+  }
+  let mut diff = 0u8;
+  for (x, y) in a.iter().zip(b.iter()) {
+    diff |= x ^ y;
+  }
+  diff == 0
+}
+
+/// Reads one newline-delimited line, same as `BufRead::read_line`, but caps
+/// it at `MAX_LINE_BYTES` so a client that never sends a `\n` can't grow the
+/// buffer unboundedly. Returns `Ok(0)` on EOF, `Ok(n)` with the line
+/// (newline included) appended to `out`, or an error if the cap was hit
+/// before a newline showed up.
+fn read_bounded_line<R: BufRead>(reader: &mut R, out: &mut String) -> std::io::Result<usize> {
+  let mut buf = Vec::new();
+  let n = reader.take(MAX_LINE_BYTES).read_until(b'\n', &mut buf)?;
+  if n == 0 {
+    return Ok(0);
+  }
+  if !buf.ends_with(b"\n") && n as u64 >= MAX_LINE_BYTES {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::InvalidData,
+      format!("line exceeded {MAX_LINE_BYTES} bytes"),
+    ));
+  }
+  out.push_str(&String::from_utf8_lossy(&buf));
+  Ok(n)
+}
+
+fn handle_client(app: AppHandle, stream: TcpStream, token: String) {
+  let peer = stream
+    .peer_addr()
+    .map(|a| a.to_string())
+    .unwrap_or_else(|_| "unknown".to_string());
+
+  // The listener is non-blocking, and on some platforms accepted sockets
+  // inherit that mode; switch back so the read timeout applies.
+  let _ = stream.set_nonblocking(false);
+  let _ = stream.set_read_timeout(Some(CLIENT_READ_TIMEOUT));
+
+  let Ok(read_stream) = stream.try_clone() else { return };
+  let mut reader = BufReader::new(read_stream);
+  let mut writer = stream;
+
+  let mut first_line = String::new();
+  match read_bounded_line(&mut reader, &mut first_line) {
+    Ok(0) | Err(_) => return,
+    Ok(_) => {}
+  }
+  if !constant_time_eq(first_line.trim().as_bytes(), token.as_bytes()) {
+    let _ = writeln!(writer, "{}", serde_json::json!({"ok": false, "error": "invalid token"}));
+    return;
+  }
+
+  let mut line = String::new();
+  loop {
+    line.clear();
+    match read_bounded_line(&mut reader, &mut line) {
+      Ok(0) | Err(_) => break,
+      Ok(_) => {}
+    }
+
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+      continue;
+    }
+
+    let command: RemoteCommand = match serde_json::from_str(trimmed) {
+      Ok(c) => c,
+      Err(e) => {
+        let _ = writeln!(writer, "{}", serde_json::json!({"ok": false, "error": format!("invalid command: {e}")}));
+        continue;
+      }
+    };
+
+    let _ = app.emit("relay://remote-command", serde_json::json!({"peer": peer, "command": trimmed}));
+
+    let body = match dispatch(&app, command) {
+      Ok(result) => serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string()),
+      Err(e) => serde_json::json!({"ok": false, "error": e}).to_string(),
+    };
+
+    if writeln!(writer, "{body}").is_err() {
+      break;
+    }
+  }
+}
+
+fn dispatch(app: &AppHandle, command: RemoteCommand) -> Result<CmdResult, String> {
+  match command {
+    RemoteCommand::Relay { relay, state, seconds, port } => {
+      if !(1..=8).contains(&relay) {
+        return Err("Relay number must be 1..8".into());
+      }
+      release_session_port(app);
+      run_relay(app, &build_relay_args(port, relay, state, seconds))
+    }
+    RemoteCommand::All { state, seconds, port } => {
+      release_session_port(app);
+      run_relay(app, &build_all_args(port, state, seconds))
+    }
+    RemoteCommand::Status { target, port } => {
+      release_session_port(app);
+      let mut args: Vec<String> = Vec::new();
+      if let Some(p) = port {
+        args.push("--port".into());
+        args.push(p);
+      }
+      args.push("status".into());
+      args.push(target);
+      run_relay(app, &args)
+    }
+    // list-ports just enumerates ports and doesn't need to open the one
+    // the session is holding, so no need to release it here.
+    RemoteCommand::ListPorts => run_relay(app, &["list-ports".to_string(), "--json".to_string()]),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn equal_tokens_match() {
+    assert!(constant_time_eq(b"secret-token", b"secret-token"));
+  }
+
+  #[test]
+  fn different_content_same_length_does_not_match() {
+    assert!(!constant_time_eq(b"secret-token", b"secret-tokeX"));
+  }
+
+  #[test]
+  fn different_length_does_not_match() {
+    assert!(!constant_time_eq(b"short", b"much-longer-token"));
+  }
+
+  #[test]
+  fn empty_inputs_match() {
+    assert!(constant_time_eq(b"", b""));
+  }
+}
@@ -0,0 +1,224 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use tauri::AppHandle;
+
+use crate::{relay_exe_path, run_relay, CmdResult};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// A long-lived `relay.exe --interactive` process, kept around so repeated
+/// commands don't pay the cost of reopening the serial port each time.
+///
+/// This assumes `relay.exe` has a REPL mode behind `--interactive` that
+/// replies with one JSON line per command. If that assumption turns out to
+/// be wrong for the `relay.exe` on disk (old build, different vendor), a
+/// reply fails to parse while the child is still alive and the session
+/// permanently falls back to spawning `relay.exe` fresh per call via
+/// [`relay_exe_path`]'s cached handle — slower, but correct. Ordinary
+/// transient failures (port not open yet, spawn/write/read errors) don't
+/// trigger this fallback; they're just reported as errors and retried as
+/// interactive on the next call.
+#[derive(Default)]
+pub struct RelaySession {
+  port: Option<String>,
+  child: Option<Child>,
+  stdin: Option<ChildStdin>,
+  stdout: Option<BufReader<ChildStdout>>,
+  interactive_supported: Option<bool>,
+}
+
+/// Why a `send_interactive` attempt failed, so `send` can tell a genuine
+/// REPL-protocol mismatch from an ordinary transient failure.
+enum InteractiveError {
+  /// Spawn/write/read failed, or the child died — could be the port not
+  /// being ready yet, a device mid-reconnect, or another process briefly
+  /// holding it. Not evidence `relay.exe --interactive` is unsupported.
+  Io(String),
+  /// The child wrote back something other than one JSON line while still
+  /// alive — strong evidence this `relay.exe` doesn't speak the REPL
+  /// protocol we expect at all.
+  BadReply(String),
+}
+
+impl From<InteractiveError> for String {
+  fn from(e: InteractiveError) -> String {
+    match e {
+      InteractiveError::Io(s) | InteractiveError::BadReply(s) => s,
+    }
+  }
+}
+
+impl RelaySession {
+  fn is_alive(&mut self) -> bool {
+    matches!(self.child.as_mut().map(|c| c.try_wait()), Some(Ok(None)))
+  }
+
+  fn spawn(&mut self, app: &AppHandle, port: Option<String>) -> Result<(), String> {
+    let exe = relay_exe_path(app)?;
+
+    let mut cmd = Command::new(&exe);
+    cmd.arg("--interactive");
+    if let Some(p) = &port {
+      cmd.arg("--port").arg(p);
+    }
+    cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null());
+
+    #[cfg(windows)]
+    {
+      cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd
+      .spawn()
+      .map_err(|e| format!("Failed to start {}: {e}", exe.display()))?;
+
+    let stdin = child.stdin.take().ok_or("relay.exe gave us no stdin")?;
+    let stdout = child.stdout.take().ok_or("relay.exe gave us no stdout")?;
+
+    self.child = Some(child);
+    self.stdin = Some(stdin);
+    self.stdout = Some(BufReader::new(stdout));
+    self.port = port;
+    Ok(())
+  }
+
+  /// Restarts the session if it has died, or if a different port was
+  /// requested than the one it was opened against.
+  fn ensure_session(&mut self, app: &AppHandle, port: &Option<String>) -> Result<(), String> {
+    let wrong_port = port.is_some() && port != &self.port;
+    if !self.is_alive() || wrong_port {
+      self.stop();
+      self.spawn(app, port.clone())?;
+    }
+    Ok(())
+  }
+
+  pub fn connect(&mut self, app: &AppHandle, port: Option<String>) -> Result<(), String> {
+    self.stop();
+    self.interactive_supported = None;
+    self.spawn(app, port)
+  }
+
+  pub fn disconnect(&mut self) {
+    self.stop();
+    self.interactive_supported = None;
+  }
+
+  /// Releases the serial port by stopping the child process, so a one-off
+  /// `relay.exe` invocation elsewhere can open it exclusively. Unlike
+  /// `disconnect`, this doesn't forget `interactive_supported` — the
+  /// session still respawns (or stays on the fallback path) lazily on the
+  /// next `send()`.
+  pub fn release(&mut self) {
+    self.stop();
+  }
+
+  /// Sends a single newline-delimited command line to the session and reads
+  /// back its JSON reply, respawning the session first if needed.
+  ///
+  /// If `relay.exe --interactive` has already proven unsupported on this
+  /// session, this skips straight to the one-shot fallback.
+  ///
+  /// `line` must not contain control characters. Callers build it by
+  /// interpolating caller-controlled fields (`state`, `target`) into the
+  /// wire protocol line; an embedded `\n` would smuggle in an extra command
+  /// and desync `send_interactive`'s one-read-per-write reply protocol, so
+  /// this is rejected centrally here rather than trusted to every caller.
+  pub fn send(&mut self, app: &AppHandle, port: Option<String>, line: &str) -> Result<CmdResult, String> {
+    if line.chars().any(|c| c.is_control()) {
+      return Err("Relay command must not contain control characters".into());
+    }
+
+    if self.interactive_supported == Some(false) {
+      return self.send_fallback(app, &port, line);
+    }
+
+    match self.send_interactive(app, port.clone(), line) {
+      Ok(result) => {
+        self.interactive_supported = Some(true);
+        Ok(result)
+      }
+      Err(InteractiveError::BadReply(e)) => {
+        // The child replied with something other than one JSON line while
+        // still alive — this relay.exe doesn't speak the --interactive
+        // protocol we expect, and retrying would just get garbled replies
+        // forever. Latch the fallback for the rest of the session.
+        self.interactive_supported = Some(false);
+        self.stop();
+        self.send_fallback(app, &port, line).map_err(|_| e)
+      }
+      Err(InteractiveError::Io(e)) => {
+        // Transient failure (port not open yet, device mid-reconnect,
+        // spawn/write/read error) — not proof relay.exe lacks --interactive
+        // support, so don't latch the fallback. The next send() will try
+        // interactive mode again.
+        Err(e)
+      }
+    }
+  }
+
+  fn send_interactive(&mut self, app: &AppHandle, port: Option<String>, line: &str) -> Result<CmdResult, InteractiveError> {
+    self
+      .ensure_session(app, &port)
+      .map_err(InteractiveError::Io)?;
+
+    let stdin = self
+      .stdin
+      .as_mut()
+      .ok_or_else(|| InteractiveError::Io("relay session has no stdin".into()))?;
+    writeln!(stdin, "{line}").map_err(|e| InteractiveError::Io(format!("Failed to write to relay session: {e}")))?;
+    stdin
+      .flush()
+      .map_err(|e| InteractiveError::Io(format!("Failed to flush relay session stdin: {e}")))?;
+
+    let stdout = self
+      .stdout
+      .as_mut()
+      .ok_or_else(|| InteractiveError::Io("relay session has no stdout".into()))?;
+    let mut reply = String::new();
+    let read = stdout
+      .read_line(&mut reply)
+      .map_err(|e| InteractiveError::Io(format!("Failed to read from relay session: {e}")))?;
+
+    if read == 0 || !self.is_alive() {
+      return Err(InteractiveError::Io("relay session closed the connection".into()));
+    }
+
+    serde_json::from_str(reply.trim())
+      .map_err(|e| InteractiveError::BadReply(format!("Invalid reply from relay session: {e}")))
+  }
+
+  /// Cache-and-reuse-the-resolved-handle fallback named in the original
+  /// request: no REPL, just a direct `relay.exe` invocation per call (still
+  /// benefiting from `relay_exe_path`'s cached lookup).
+  fn send_fallback(&mut self, app: &AppHandle, port: &Option<String>, line: &str) -> Result<CmdResult, String> {
+    let mut args: Vec<String> = Vec::new();
+    if let Some(p) = port {
+      args.push("--port".into());
+      args.push(p.clone());
+    }
+    args.extend(line.split_whitespace().map(str::to_string));
+
+    run_relay(app, &args)
+  }
+
+  fn stop(&mut self) {
+    if let Some(mut child) = self.child.take() {
+      let _ = child.kill();
+      let _ = child.wait();
+    }
+    self.stdin = None;
+    self.stdout = None;
+    self.port = None;
+  }
+}
+
+impl Drop for RelaySession {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}